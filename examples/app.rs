@@ -4,84 +4,85 @@ This example shows how to integrate the `HistoryTree` with application data.
 
 extern crate history_tree;
 
-use history_tree::HistoryTree;
+use std::collections::HashMap;
+
+use history_tree::{HistoryTree, NodeId};
 
 fn main() {
     let mut app = App::new();
     let root = app.root();
-    let mut assets = app.add("asssets".into(), root);
+    let assets = app.add("asssets".into(), root);
     let _syntax = app.add("syntax".into(), assets);
     app.print(assets, 0);
 
     println!("---- change ----");
-    app.change("assets".into(), &mut assets);
+    app.change("assets".into(), assets);
     app.print(assets, 0);
 
     println!("---- undo ----");
     app.undo();
-    let assets = app.children(root)[0];
     app.print(assets, 0);
 
     println!("---- add ----");
     let _hello = app.add("hello".into(), assets);
-    let assets = app.children(root)[0];
     app.print(assets, 0);
 }
 
 /// Stores application data.
 pub struct App {
     ht: HistoryTree,
-    text: Vec<String>,
+    text: HashMap<usize, String>,
 }
 
 impl App {
     /// Creates a new `App`.
     pub fn new() -> App {
+        let mut text = HashMap::new();
+        text.insert(0, "root".into());
         App {
             ht: HistoryTree::new(),
-            // Add dummy root to align indices.
-            text: vec!["root".into()],
+            text: text,
         }
     }
 
     /// Gets the root.
-    pub fn root(&self) -> usize {self.ht.root()}
+    pub fn root(&self) -> NodeId {self.ht.id_of(self.ht.root())}
 
     /// Adds a node.
-    pub fn add(&mut self, text: String, parent: usize) -> usize {
-        let cursor = self.ht.cursor();
-        self.text.truncate(cursor + 1);
-
-        self.text.push(text);
-        self.ht.add(parent)
+    pub fn add(&mut self, text: String, parent: NodeId) -> NodeId {
+        let parent = self.ht.current(parent).expect("parent node does not exist");
+        let node = self.ht.add(parent);
+        self.text.insert(node, text);
+        self.ht.id_of(node)
     }
 
     /// Changes a node.
-    pub fn change(&mut self, text: String, node: &mut usize) {
-        let cursor = self.ht.cursor();
-        self.text.truncate(cursor + 1);
-
-        self.text.push(text);
-        self.ht.change(node);
+    pub fn change(&mut self, text: String, node: NodeId) {
+        let mut node = self.ht.current(node).expect("node does not exist");
+        self.ht.change(&mut node);
+        self.text.insert(node, text);
     }
 
     /// Deletes a node.
-    pub fn delete(&mut self, node: usize) {
-        let cursor = self.ht.cursor();
-        self.text.truncate(cursor + 1);
-
+    pub fn delete(&mut self, node: NodeId) {
+        let node = self.ht.current(node).expect("node does not exist");
         self.ht.delete(node);
     }
 
     /// Prints out data to standard output.
-    pub fn print(&self, parent: usize, tabs: u32) {
+    pub fn print(&self, node: NodeId, tabs: u32) {
+        let node = match self.ht.current(node) {
+            Some(node) => node,
+            None => return,
+        };
+
         if tabs > 0 {
             for _ in 0..tabs - 1 {print!("  ")}
             print!("|-");
         }
-        println!("{}", self.text[parent]);
-        for &ch in &self.ht.children(parent) {
-            self.print(ch, tabs + 1);
+        println!("{}", self.text[&node]);
+        for child in self.ht.children(node) {
+            self.print(self.ht.id_of(child), tabs + 1);
         }
     }
 
@@ -92,7 +93,11 @@ impl App {
     pub fn redo(&mut self) {self.ht.redo()}
 
     /// Gets children.
-    pub fn children(&self, parent: usize) -> Vec<usize> {
-        self.ht.children(parent)
+    pub fn children(&self, node: NodeId) -> Vec<NodeId> {
+        let node = match self.ht.current(node) {
+            Some(node) => node,
+            None => return vec![],
+        };
+        self.ht.children(node).into_iter().map(|c| self.ht.id_of(c)).collect()
     }
 }
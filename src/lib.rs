@@ -22,16 +22,71 @@
 //! When a record is pointed to by a new active record, it gets overriden.
 //! A record is considered child of a parent when it points to the parent or any previous version.
 //!
+//! Editing after an `undo` does not throw away the undone records.
+//! Instead, the new record branches off the cursor, so every abandoned future
+//! stays reachable as a branch in the tree. `redo` follows the most recently
+//! created branch by default, and `children_revisions` lets callers discover
+//! the other branches at a given point.
+//!
 //! `.add`/`.change`/`.delete` are `O(1)` operations.
 //!
-//! `.children` is `O(N * M)` operation where `N` is number of parent versions and `M` is records.
+//! `.children` looks candidates up in an index that is maintained
+//! incrementally by `.add`/`.change`/`.delete`, so it avoids scanning
+//! every record in the tree. It still recomputes the active path from
+//! the cursor on every call, so its real cost is `O(depth)` plus the
+//! number of records ever attached to the parent or its earlier versions,
+//! not `O(1)`; recursively walking a whole tree
+//! through repeated `.children` calls (as `.print` does) costs
+//! `O(depth)` per record visited, not `O(1)`. Debug builds also check
+//! every result against a full scan of the active records.
+//!
+//! With the `serde` feature enabled, `Record` and `HistoryTree` can be
+//! serialized and deserialized, so the history can persist across sessions.
+//! Use `validate` on a loaded tree before using it, since a file from an
+//! untrusted source could otherwise contain out-of-bounds indices.
+//!
+//! `set_coalesce_window` merges consecutive `change` calls on the same
+//! node into a single undo step when they land within the given window,
+//! so editors that call `change` on every keystroke don't produce a
+//! history where each undo only reverts one character. `begin_group` and
+//! `commit_group` let callers force a boundary regardless of timing.
 //!
-//! To make `.children` fast, records are stored with only indices.
+//! A `Bookmark` captures a node and the revision it was recorded at, so
+//! a UI can drop a marker on an interesting state and reliably jump back
+//! to the live version of that node later, even across undos, redos,
+//! and branch switches.
 
 #![deny(missing_docs)]
 
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A stable identity for a node that survives `change`.
+///
+/// Unlike a record index, which changes every time `change` is called on
+/// a node, a `NodeId` stays the same across all versions of that node,
+/// so callers can hold onto it across edits and undo/redo.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeId(pub usize);
+
+/// A marker that captures a node and the revision it was recorded at.
+///
+/// Combine with `go_to` to jump back to the branch a bookmark was made
+/// on, and `validate_bookmark` to resolve it to the node's live record
+/// index even after further edits, undos, or branch switches.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bookmark {
+    /// The stable id of the bookmarked node.
+    pub id: NodeId,
+    /// The cursor position when the bookmark was made.
+    pub cursor: usize,
+}
+
 /// Stores information about a node relation.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Record {
     /// Previous version.
     pub prev: usize,
@@ -39,18 +94,41 @@ pub struct Record {
     pub parent: usize,
     /// Removes previous nodes.
     pub remove: bool,
+    /// The cursor this record branched off from.
+    pub branch: usize,
+    /// The stable id of the node this record is a version of.
+    pub id: NodeId,
+    /// When this record was created, used for coalescing.
+    #[cfg_attr(feature = "serde", serde(skip, default = "Instant::now"))]
+    pub timestamp: Instant,
+    /// The undo group this record belongs to, used for coalescing.
+    pub group: usize,
 }
 
 /// Stores information about history tree relations.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HistoryTree {
     /// Stores records.
     pub records: Vec<Record>,
     /// History cursor.
-    /// Points to an index of records where all previous changes
-    /// are active, and those after are inactive.
-    /// When set to `None`, it is assumed to point to the latest version.
-    pub cursor: Option<usize>,
+    /// Points to the record index that is currently active.
+    pub cursor: usize,
+    /// Maps a record index to the record indices of every child created
+    /// with that exact version as `parent`, so `children` can walk a
+    /// parent's own version chain instead of scanning every record.
+    child_index: HashMap<usize, Vec<usize>>,
+    /// How close in time two `change`s to the same node must be to get
+    /// coalesced into one record. `None` disables coalescing.
+    coalesce_window: Option<Duration>,
+    /// The current undo group, bumped by `begin_group`/`commit_group`.
+    current_group: usize,
+}
+
+impl Default for HistoryTree {
+    fn default() -> HistoryTree {
+        HistoryTree::new()
+    }
 }
 
 impl HistoryTree {
@@ -61,8 +139,15 @@ impl HistoryTree {
                 prev: 0, // Points back to itself.
                 parent: 0, // Points back to itself.
                 remove: false,
+                branch: 0, // Points back to itself.
+                id: NodeId(0),
+                timestamp: Instant::now(),
+                group: 0,
             }],
-            cursor: None,
+            cursor: 0,
+            child_index: HashMap::new(),
+            coalesce_window: None,
+            current_group: 0,
         }
     }
 
@@ -70,63 +155,266 @@ impl HistoryTree {
     pub fn root(&self) -> usize {0}
 
     /// Gets the cursor.
-    pub fn cursor(&self) -> usize {
-        self.cursor.unwrap_or(self.records.len() - 1)
-    }
+    pub fn cursor(&self) -> usize {self.cursor}
 
     /// Add new node.
     pub fn add(&mut self, parent: usize) -> usize {
-        let cursor = self.cursor();
-        self.records.truncate(cursor + 1);
-        self.cursor = None;
-
+        let branch = self.cursor;
         let n = self.records.len();
         self.records.push(Record {
             prev: n, // Points back to itself.
-            parent: parent,
+            parent,
             remove: false,
+            branch,
+            id: NodeId(n),
+            timestamp: Instant::now(),
+            group: self.current_group,
         });
+        self.cursor = n;
+        self.index_child(parent, n);
         n
     }
 
     /// Change node.
+    ///
+    /// If a coalesce window is set and this is called again on the same
+    /// node, in the same undo group, within the window, the previous
+    /// record is reused in place instead of pushing a new one.
     pub fn change(&mut self, node: &mut usize) {
-        let cursor = self.cursor();
-        self.records.truncate(cursor + 1);
-        self.cursor = None;
+        let now = Instant::now();
+        if let Some(window) = self.coalesce_window {
+            let prev = &self.records[*node];
+            if self.cursor == *node && prev.group == self.current_group &&
+               now.duration_since(prev.timestamp) <= window {
+                self.records[*node].timestamp = now;
+                return;
+            }
+        }
 
+        let branch = self.cursor;
         let n = self.records.len();
         let parent = self.records[*node].parent;
+        let id = self.records[*node].id;
         self.records.push(Record {
             prev: *node,
-            parent: parent,
+            parent,
             remove: false,
+            branch,
+            id,
+            timestamp: now,
+            group: self.current_group,
         });
+        self.cursor = n;
+        self.index_child(parent, n);
         *node = n
     }
 
     /// Delete node.
     pub fn delete(&mut self, node: usize) {
-        let cursor = self.cursor();
-        self.records.truncate(cursor + 1);
-        self.cursor = None;
-
+        let branch = self.cursor;
+        let n = self.records.len();
         let parent = self.records[node].parent;
+        let id = self.records[node].id;
         self.records.push(Record {
             prev: node,
-            parent: parent,
+            parent,
             remove: true,
+            branch,
+            id,
+            timestamp: Instant::now(),
+            group: self.current_group,
         });
+        self.cursor = n;
+        self.index_child(parent, n);
+    }
+
+    /// Sets how close in time two `change`s to the same node must be to
+    /// get coalesced into a single undo step. `None` disables coalescing.
+    pub fn set_coalesce_window(&mut self, window: Option<Duration>) {
+        self.coalesce_window = window;
+    }
+
+    /// Starts a new undo group, so the next edit won't be coalesced with
+    /// whatever came before it.
+    pub fn begin_group(&mut self) {
+        self.current_group += 1;
+    }
+
+    /// Closes the current undo group, so the next edit won't be
+    /// coalesced backward into it. Equivalent to `begin_group`; call
+    /// whichever reads better at the boundary, e.g. on save or when the
+    /// cursor moves.
+    pub fn commit_group(&mut self) {
+        self.current_group += 1;
+    }
+
+    /// Registers `child` as a child of the exact version `parent`.
+    fn index_child(&mut self, parent: usize, child: usize) {
+        self.child_index.entry(parent).or_default().push(child);
+    }
+
+    /// Gets the stable id of a node's record.
+    pub fn id_of(&self, record: usize) -> NodeId {
+        self.records[record].id
+    }
+
+    /// Gets the current record index of the node with the given stable id.
+    ///
+    /// Returns `None` if no record with that id is reachable from the
+    /// cursor, or if the most recent one reachable was deleted.
+    pub fn current(&self, id: NodeId) -> Option<usize> {
+        let &latest = self.active().iter()
+            .rfind(|&&i| self.records[i].id == id)?;
+        if self.records[latest].remove {None} else {Some(latest)}
+    }
+
+    /// Bookmarks `node`, capturing its stable id and the current cursor.
+    pub fn bookmark(&self, node: usize) -> Bookmark {
+        Bookmark {id: self.id_of(node), cursor: self.cursor}
+    }
+
+    /// Resolves a bookmark to its node's current record index.
+    ///
+    /// Returns `None` if the node has been deleted or lives on a branch
+    /// that isn't reachable from the cursor. To jump back to the branch
+    /// the bookmark was made on first, call `go_to(bookmark.cursor)`.
+    pub fn validate_bookmark(&self, b: Bookmark) -> Option<usize> {
+        self.current(b.id)
+    }
+
+    /// Collects the active records: the path from the root to the cursor.
+    ///
+    /// Recomputed on every call (`O(depth)`); `children` calls this once
+    /// per invocation, so repeatedly walking a subtree costs `O(depth)`
+    /// per record visited rather than `O(1)`.
+    fn active(&self) -> Vec<usize> {
+        let mut active = vec![];
+        let mut node = self.cursor;
+        loop {
+            active.push(node);
+            let b = self.records[node].branch;
+            if b == node {break;}
+            node = b;
+        }
+        active.sort();
+        active
+    }
+
+    /// Checks that the tree is well-formed.
+    ///
+    /// Verifies that the root record is self-referential, that every
+    /// `prev`/`parent`/`branch` index and the cursor are in bounds, that
+    /// every `branch` chain terminates at the root and every `prev` chain
+    /// at a node's first version without cycling, and that node ids and
+    /// the child index agree with the records, so a tree loaded from an untrusted source
+    /// can't cause a panic or an infinite loop in `children`, `active`,
+    /// or the navigation methods.
+    pub fn validate(&self) -> bool {
+        if self.records.is_empty() {return false;}
+
+        let root = &self.records[0];
+        if root.prev != 0 || root.parent != 0 || root.branch != 0 {return false;}
+
+        let len = self.records.len();
+        if self.cursor >= len {return false;}
+        if !self.records.iter().all(|r| r.prev < len && r.parent < len && r.branch < len && r.id.0 < len) {
+            return false;
+        }
+
+        let mut index: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, r) in self.records.iter().enumerate().skip(1) {
+            // Only the root may end a `branch` chain, and every version of
+            // a node carries the id of the version it was made from.
+            if r.branch == i {return false;}
+            let id = if r.prev == i {NodeId(i)} else {self.records[r.prev].id};
+            if r.id != id {return false;}
+            index.entry(r.parent).or_default().push(i);
+        }
+        if index != self.child_index {return false;}
+
+        (0..len).all(|i| self.chain_terminates(i, |r| r.branch) && self.chain_terminates(i, |r| r.prev))
+    }
+
+    /// Checks that walking `next` from `start` reaches a self-referential
+    /// record within `self.records.len()` steps, instead of cycling forever.
+    fn chain_terminates(&self, mut node: usize, next: impl Fn(&Record) -> usize) -> bool {
+        for _ in 0..self.records.len() {
+            let n = next(&self.records[node]);
+            if n == node {return true;}
+            node = n;
+        }
+        false
+    }
+
+    /// Gets the revisions that branched off directly from `rev`.
+    ///
+    /// The records are returned in the order they were created,
+    /// so the most recently created branch is always last.
+    pub fn children_revisions(&self, rev: usize) -> Vec<usize> {
+        self.records.iter()
+            .enumerate()
+            .filter(|&(i, r)| i != rev && r.branch == rev)
+            .map(|(i, _)| i)
+            .collect()
     }
 
     /// Gets the names of children.
     pub fn children(&self, parent: usize) -> Vec<usize> {
-        let cursor = self.cursor.unwrap_or(self.records.len() - 1);
-        if cursor < parent {return vec![];}
+        let active = self.active();
+        if *active.last().unwrap() < parent {return vec![];}
 
-        let nodes: Vec<usize> = self.records[1..cursor + 1].iter()
-            .enumerate()
-            .filter(|&(_, r)| {
+        // A record is a child of `parent` when it was created with
+        // `parent` itself, or any earlier version of it, as its literal
+        // `parent` field — so walk `parent`'s own `prev` chain, looking
+        // up each version in the index, instead of trusting every child
+        // ever attached under the node's `NodeId` regardless of which
+        // version was queried.
+        let mut latest: HashMap<NodeId, usize> = HashMap::new();
+        let mut node = parent;
+        loop {
+            if let Some(candidates) = self.child_index.get(&node) {
+                for &c in candidates {
+                    if active.binary_search(&c).is_err() {continue;}
+                    let cid = self.records[c].id;
+                    let slot = latest.entry(cid).or_insert(c);
+                    if c > *slot {*slot = c;}
+                }
+            }
+            let prev = self.records[node].prev;
+            if prev == node {break;}
+            node = prev;
+        }
+
+        let mut nodes: Vec<usize> = latest.into_iter()
+            .filter(|&(_, i)| !self.records[i].remove)
+            .map(|(_, i)| i)
+            .collect();
+        nodes.sort();
+
+        #[cfg(debug_assertions)]
+        {
+            let mut naive = self.children_naive(parent);
+            naive.sort();
+            debug_assert_eq!(nodes, naive, "children index diverged from the naive scan");
+        }
+
+        nodes
+    }
+
+    /// Gets the names of children by scanning every active record.
+    ///
+    /// This is the original `O(N * M)` implementation, kept as a
+    /// correctness oracle that `children` checks itself against in
+    /// debug builds.
+    #[cfg(debug_assertions)]
+    fn children_naive(&self, parent: usize) -> Vec<usize> {
+        let active = self.active();
+        if *active.last().unwrap() < parent {return vec![];}
+
+        let nodes: Vec<usize> = active.iter()
+            .filter(|&&i| i >= 1)
+            .filter(|&&i| {
+                    let r = &self.records[i];
                     let mut node = parent;
                     loop {
                         if r.parent == node {return true;}
@@ -136,7 +424,7 @@ impl HistoryTree {
                     }
                     false
                 })
-            .map(|(i, _)| i + 1)
+            .cloned()
             .collect();
 
         // Remove the older versions.
@@ -157,25 +445,71 @@ impl HistoryTree {
             .collect()
     }
 
+    /// Moves the cursor directly to `target`, crossing branches if needed.
+    ///
+    /// Returns `false` and leaves the cursor untouched if `target` is not
+    /// a valid record index, instead of corrupting the cursor and
+    /// panicking later in `children`/`undo`/`redo`.
+    pub fn go_to(&mut self, target: usize) -> bool {
+        if target >= self.records.len() {return false;}
+        self.cursor = target;
+        true
+    }
+
+    /// Finds the ordered sequence of revisions the cursor lands on while
+    /// moving from its current position to `target`.
+    ///
+    /// The path goes back to the common ancestor of the cursor and
+    /// `target` in the undo tree, then forward to `target`. Each entry is
+    /// a revision landed on along the way, in order; the starting cursor
+    /// is never included (it's already current), and `target` is always
+    /// the last entry, so callers replaying application state can apply
+    /// each entry's delta in turn and finish on `target`.
+    pub fn path_to(&self, target: usize) -> Vec<usize> {
+        if target == self.cursor {return vec![];}
+
+        let ancestors = |mut node: usize| {
+            let mut path = vec![node];
+            loop {
+                let b = self.records[node].branch;
+                if b == node {break;}
+                node = b;
+                path.push(node);
+            }
+            path
+        };
+        let from = ancestors(self.cursor);
+        let to = ancestors(target);
+
+        let common = from.iter().find(|n| to.contains(n)).cloned().unwrap_or(self.root());
+        let common_in_from = from.iter().position(|&n| n == common).unwrap();
+        let common_in_to = to.iter().position(|&n| n == common).unwrap();
+
+        // Undo leg: revisions landed on walking back from the cursor to
+        // the common ancestor, excluding the cursor and including the
+        // common ancestor itself.
+        let mut path: Vec<usize> = (1..=common_in_from).map(|i| from[i]).collect();
+
+        // Redo leg: revisions landed on walking forward from the common
+        // ancestor to `target`, excluding the common ancestor (already
+        // reached above, or already the cursor) and including `target`.
+        path.extend((0..common_in_to).rev().map(|i| to[i]));
+        path
+    }
+
     /// Goes back one step in history.
     pub fn undo(&mut self) {
-        self.cursor = if let Some(index) = self.cursor {
-            if index > 0 {Some(index - 1)}
-            else if self.records.len() == 0 {None}
-            else {Some(0)}
-        } else {
-            if self.records.len() == 0 {None}
-            else {Some(self.records.len() - 2)}
-        };
+        let b = self.records[self.cursor].branch;
+        if b != self.cursor {self.cursor = b;}
     }
 
     /// Goes forward one step in history.
+    ///
+    /// When the cursor has branched, this follows the most recently
+    /// created branch. Use `go_to` to follow a different branch.
     pub fn redo(&mut self) {
-        self.cursor = if let Some(index) = self.cursor {
-            if index + 1 >= self.records.len() {None}
-            else {Some(index + 1)}
-        } else {
-            None
+        if let Some(&next) = self.children_revisions(self.cursor).iter().max() {
+            self.cursor = next;
         }
     }
 
@@ -192,3 +526,292 @@ impl HistoryTree {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_to_does_not_duplicate_target_after_undo() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(0);
+        ht.change(&mut a);
+        ht.undo();
+        assert_eq!(ht.path_to(2), vec![2]);
+    }
+
+    #[test]
+    fn path_to_does_not_include_cursor_on_pure_undo() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(0);
+        ht.change(&mut a);
+        // Landing on 1 is a single undo step; the starting cursor (2)
+        // must not appear, matching the forward (redo) convention.
+        assert_eq!(ht.path_to(1), vec![1]);
+    }
+
+    #[test]
+    fn path_to_across_branches_includes_the_pivot_once() {
+        let mut ht = HistoryTree::new();
+        ht.add(0); // record 1
+        ht.undo();
+        ht.add(0); // record 2, a second branch off the root
+
+        // From record 2, reaching record 1 undoes back to the root (0)
+        // then redoes forward into the other branch, landing on 0 then 1.
+        assert_eq!(ht.path_to(1), vec![0, 1]);
+    }
+
+    #[test]
+    fn children_of_stale_parent_version_excludes_later_children() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(0);
+        let old_a = a;
+        ht.change(&mut a);
+        ht.add(a);
+
+        // `old_a` is a superseded version of the same node; a child
+        // attached after the `change` belongs to the new version only.
+        assert_eq!(ht.children(old_a), Vec::<usize>::new());
+        assert_eq!(ht.children(old_a), ht.children_naive(old_a));
+        assert_eq!(ht.children(a), vec![3]);
+        assert_eq!(ht.children(a), ht.children_naive(a));
+    }
+
+    #[test]
+    fn children_matches_naive_after_branching() {
+        let mut ht = HistoryTree::new();
+        let root = ht.root();
+        let mut a = ht.add(root);
+        let _b = ht.add(root);
+        ht.change(&mut a);
+        assert_eq!(ht.children(root), ht.children_naive(root));
+
+        // Edit after undo, creating a second branch off the same point.
+        ht.undo();
+        let _c = ht.add(root);
+        assert_eq!(ht.children(root), ht.children_naive(root));
+    }
+
+    #[test]
+    fn children_matches_naive_across_undo_and_redo() {
+        let mut ht = HistoryTree::new();
+        let root = ht.root();
+        let mut a = ht.add(root);
+        ht.change(&mut a);
+        ht.undo();
+        assert_eq!(ht.children(root), ht.children_naive(root));
+
+        ht.redo();
+        assert_eq!(ht.children(root), ht.children_naive(root));
+    }
+
+    #[test]
+    fn children_matches_naive_with_delete() {
+        let mut ht = HistoryTree::new();
+        let root = ht.root();
+        let a = ht.add(root);
+        let _b = ht.add(root);
+        ht.delete(a);
+        assert_eq!(ht.children(root), ht.children_naive(root));
+        assert!(!ht.children(root).contains(&a));
+    }
+
+    #[test]
+    fn children_matches_naive_with_coalesced_edits() {
+        let mut ht = HistoryTree::new();
+        let root = ht.root();
+        let mut a = ht.add(root);
+        ht.set_coalesce_window(Some(Duration::from_secs(5)));
+
+        // The first `change` coalesces into `a`'s own record since it is
+        // still the cursor, and the following two coalesce into that.
+        let before = ht.records.len();
+        ht.change(&mut a);
+        ht.change(&mut a);
+        ht.change(&mut a);
+        assert_eq!(ht.records.len(), before);
+        assert_eq!(ht.children(root), ht.children_naive(root));
+    }
+
+    #[test]
+    fn validate_accepts_well_formed_tree() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(0);
+        ht.change(&mut a);
+        ht.undo();
+        ht.add(0);
+
+        assert!(ht.validate());
+    }
+
+    #[test]
+    fn validate_rejects_out_of_bounds_cursor() {
+        let mut ht = HistoryTree::new();
+        ht.add(0);
+        ht.cursor = 100;
+
+        assert!(!ht.validate());
+    }
+
+    #[test]
+    fn validate_rejects_out_of_bounds_index() {
+        let mut ht = HistoryTree::new();
+        ht.add(0);
+        ht.records[1].parent = 100;
+
+        assert!(!ht.validate());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_round_trip_preserves_validate() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(ht.root());
+        ht.change(&mut a);
+        ht.undo();
+
+        let json = serde_json::to_string(&ht).unwrap();
+        let back: HistoryTree = serde_json::from_str(&json).unwrap();
+
+        assert!(back.validate());
+        assert_eq!(back.records.len(), ht.records.len());
+        assert_eq!(back.cursor, ht.cursor);
+    }
+
+    #[test]
+    fn validate_rejects_branch_cycle() {
+        let mut ht = HistoryTree::new();
+        ht.add(0);
+        ht.add(0);
+
+        // Corrupt the branch chain into a cycle between records 1 and 2,
+        // as could happen with a hand-edited or corrupted serialized file.
+        ht.records[1].branch = 2;
+        ht.records[2].branch = 1;
+
+        assert!(!ht.validate());
+    }
+
+    #[test]
+    fn validate_rejects_prev_cycle() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(0);
+        ht.change(&mut a);
+
+        // Corrupt the prev chain into a cycle between records 1 and 2.
+        ht.records[1].prev = 2;
+        ht.records[2].prev = 1;
+
+        assert!(!ht.validate());
+    }
+
+    #[test]
+    fn bookmark_survives_undo_and_redo() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(ht.root());
+        let mark = ht.bookmark(a);
+
+        ht.change(&mut a);
+        assert_eq!(ht.validate_bookmark(mark), Some(a));
+
+        ht.undo();
+        assert_eq!(ht.validate_bookmark(mark), Some(1));
+
+        ht.redo();
+        assert_eq!(ht.validate_bookmark(mark), Some(a));
+    }
+
+    #[test]
+    fn bookmark_resolves_to_none_after_delete() {
+        let mut ht = HistoryTree::new();
+        let a = ht.add(ht.root());
+        let mark = ht.bookmark(a);
+
+        ht.delete(a);
+        assert_eq!(ht.validate_bookmark(mark), None);
+    }
+
+    #[test]
+    fn bookmark_resolves_to_none_on_abandoned_branch() {
+        let mut ht = HistoryTree::new();
+        let a = ht.add(ht.root());
+        let mark = ht.bookmark(a);
+
+        // Switch to a sibling branch; `a`'s branch is no longer reachable.
+        ht.undo();
+        ht.add(ht.root());
+        assert_eq!(ht.validate_bookmark(mark), None);
+
+        // Jumping back to the branch the bookmark was made on resolves it again.
+        assert!(ht.go_to(mark.cursor));
+        assert_eq!(ht.validate_bookmark(mark), Some(a));
+    }
+
+    #[test]
+    fn redo_follows_most_recently_created_branch() {
+        let mut ht = HistoryTree::new();
+        let root = ht.root();
+        ht.add(root); // record 1, first branch off the root.
+        ht.undo();
+        ht.add(root); // record 2, a second, more recent branch off the root.
+
+        assert_eq!(ht.children_revisions(root), vec![1, 2]);
+
+        ht.undo();
+        ht.redo();
+        assert_eq!(ht.cursor(), 2);
+    }
+
+    #[test]
+    fn change_outside_coalesce_window_does_not_coalesce() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(ht.root());
+        ht.set_coalesce_window(Some(Duration::from_millis(1)));
+
+        let before = ht.records.len();
+        ht.change(&mut a); // Coalesces into `a`'s own record since it is still the cursor.
+        std::thread::sleep(Duration::from_millis(20));
+        ht.change(&mut a); // Past the window now, so this must land in a new record.
+        assert_eq!(ht.records.len(), before + 1);
+    }
+
+    #[test]
+    fn begin_group_forces_a_split_within_the_window() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(ht.root());
+        ht.set_coalesce_window(Some(Duration::from_secs(5)));
+
+        let before = ht.records.len();
+        ht.change(&mut a); // Coalesces into `a`'s own record.
+        ht.begin_group();
+        ht.change(&mut a); // New group boundary forces a split despite being within the window.
+        assert_eq!(ht.records.len(), before + 1);
+    }
+
+    #[test]
+    fn commit_group_forces_a_split_within_the_window() {
+        let mut ht = HistoryTree::new();
+        let mut a = ht.add(ht.root());
+        ht.set_coalesce_window(Some(Duration::from_secs(5)));
+
+        let before = ht.records.len();
+        ht.change(&mut a); // Coalesces into `a`'s own record.
+        ht.commit_group();
+        ht.change(&mut a); // New group boundary forces a split despite being within the window.
+        assert_eq!(ht.records.len(), before + 1);
+    }
+
+    #[test]
+    fn go_to_rejects_out_of_bounds_target() {
+        let mut ht = HistoryTree::new();
+        let _a = ht.add(ht.root());
+        let cursor = ht.cursor();
+
+        assert!(!ht.go_to(100));
+        assert_eq!(ht.cursor(), cursor);
+
+        assert!(ht.go_to(0));
+        assert_eq!(ht.cursor(), 0);
+    }
+}